@@ -1,14 +1,17 @@
 //! Inter-Integrated Circuit (I2C) bus
 
-use stm32f103xx::{I2C1, I2C2};
+use cortex_m::asm;
+use stm32f103xx::{GPIOB, I2C1, I2C2};
 
 use gpio::gpioa::{PA10, PA9};
 use gpio::gpiob::{PB10, PB11, PB6, PB7, PB8, PB9};
-use gpio::{Alternate, PushPull};
-use hal::blocking::i2c::{Write, WriteRead};
+use gpio::{Alternate, OpenDrain};
+use hal::blocking::i2c::{Read, Write, WriteRead};
 use rcc::{APB1, Clocks};
 use time::Hertz;
 
+use dma::{dma1, Static, Transfer as DmaTransfer, R, W};
+
 /// I2C error
 #[derive(Debug)]
 pub enum Error {
@@ -16,6 +19,8 @@ pub enum Error {
     Bus,
     /// Arbitration loss
     Arbitration,
+    /// No acknowledge
+    Acknowledge,
     // Overrun, // slave mode only
     // Pec, // SMBUS mode only
     // Timeout, // SMBUS mode only
@@ -23,27 +28,113 @@ pub enum Error {
     #[doc(hidden)] _Extensible,
 }
 
+/// I2C SCL duty cycle in fast mode
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DutyCycle {
+    /// 2:1 (t_low:t_high) duty cycle
+    Ratio2to1,
+    /// 16:9 duty cycle
+    Ratio16to9,
+}
+
+/// I2C bus mode
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mode {
+    /// Standard mode (up to 100 kHz)
+    Standard {
+        /// Bus frequency
+        frequency: Hertz,
+    },
+    /// Fast mode (up to 400 kHz)
+    Fast {
+        /// Bus frequency
+        frequency: Hertz,
+        /// SCL duty cycle
+        duty_cycle: DutyCycle,
+    },
+}
+
+impl Mode {
+    /// The SCL frequency selected by this mode
+    pub fn frequency(&self) -> Hertz {
+        match *self {
+            Mode::Standard { frequency } => frequency,
+            Mode::Fast { frequency, .. } => frequency,
+        }
+    }
+}
+
+impl<F> From<F> for Mode
+where
+    F: Into<Hertz>,
+{
+    fn from(frequency: F) -> Self {
+        let frequency = frequency.into();
+        if frequency.0 <= 100_000 {
+            Mode::Standard { frequency }
+        } else {
+            Mode::Fast {
+                frequency,
+                duty_cycle: DutyCycle::Ratio2to1,
+            }
+        }
+    }
+}
+
 // FIXME these should be "closed" traits
 /// SCL pin -- DO NOT IMPLEMENT THIS TRAIT
-pub unsafe trait SclPin<I2C> {}
+pub unsafe trait SclPin<I2C> {
+    /// Bit of this pin within GPIOB
+    const BIT: u8;
+}
 
 /// SDA pin -- DO NOT IMPLEMENT THIS TRAIT
-pub unsafe trait SdaPin<I2C> {}
+pub unsafe trait SdaPin<I2C> {
+    /// Bit of this pin within GPIOB
+    const BIT: u8;
+}
 
-unsafe impl SclPin<I2C1> for PB6<Alternate<PushPull>> {}
-unsafe impl SclPin<I2C1> for PB8<Alternate<PushPull>> {}
+unsafe impl SclPin<I2C1> for PB6<Alternate<OpenDrain>> {
+    const BIT: u8 = 6;
+}
+unsafe impl SclPin<I2C1> for PB8<Alternate<OpenDrain>> {
+    const BIT: u8 = 8;
+}
 
-unsafe impl SclPin<I2C2> for PB10<Alternate<PushPull>> {}
+unsafe impl SclPin<I2C2> for PB10<Alternate<OpenDrain>> {
+    const BIT: u8 = 10;
+}
 
-unsafe impl SdaPin<I2C1> for PB7<Alternate<PushPull>> {}
-unsafe impl SdaPin<I2C1> for PB9<Alternate<PushPull>> {}
+unsafe impl SdaPin<I2C1> for PB7<Alternate<OpenDrain>> {
+    const BIT: u8 = 7;
+}
+unsafe impl SdaPin<I2C1> for PB9<Alternate<OpenDrain>> {
+    const BIT: u8 = 9;
+}
 
-unsafe impl SdaPin<I2C2> for PB11<Alternate<PushPull>> {}
+unsafe impl SdaPin<I2C2> for PB11<Alternate<OpenDrain>> {
+    const BIT: u8 = 11;
+}
 
 /// I2C peripheral operating in master mode
-pub struct I2c<I2C, PINS> {
+///
+/// The `TX`/`RX` type parameters carry the DMA1 channels once the peripheral has been paired with
+/// them through [`I2c::with_dma`]; by default they are `()`, i.e. the peripheral is CPU-driven.
+pub struct I2c<I2C, PINS, TX = (), RX = ()> {
     i2c: I2C,
     pins: PINS,
+    tx: TX,
+    rx: RX,
+}
+
+/// A DMA-driven I2C transfer in progress
+///
+/// The DMA channel streams the data phase in the background; the CPU is free until the caller
+/// either polls [`is_done`](I2cDmaTransfer::is_done) or calls [`wait`](I2cDmaTransfer::wait).
+/// Both `wait` and dropping the handle emit the closing `STOP`; `wait` also yields the buffer
+/// and peripheral back, while dropping the handle drops them.
+pub struct I2cDmaTransfer<MODE, BUFFER, PAYLOAD> {
+    inner: Option<DmaTransfer<MODE, BUFFER, PAYLOAD>>,
 }
 
 macro_rules! busy_wait {
@@ -55,6 +146,11 @@ macro_rules! busy_wait {
                 return Err(Error::Bus);
             } else if sr1.arlo().bit_is_set() {
                 return Err(Error::Arbitration);
+            } else if sr1.af().bit_is_set() {
+                // the addressed device did not acknowledge; clear AF and release the bus
+                $i2c.sr1.modify(|_, w| w.af().clear_bit());
+                $i2c.cr1.modify(|_, w| w.stop().set_bit());
+                return Err(Error::Acknowledge);
             } else if sr1.$flag().bit_is_set() {
                 break;
             } else {
@@ -65,18 +161,18 @@ macro_rules! busy_wait {
 }
 
 macro_rules! hal {
-    ($($I2CX:ident: ($i2cX:ident, $i2cXen:ident, $i2cXrst:ident),)+) => {
+    ($($I2CX:ident: ($i2cX:ident, $i2cXen:ident, $i2cXrst:ident, $txch:ident, $rxch:ident),)+) => {
         $(
             impl<SCL, SDA> I2c<$I2CX, (SCL, SDA)> {
                 /// Configures the I2C peripheral to work in master mode
-                pub fn $i2cX<F>(
+                pub fn $i2cX<M>(
                     i2c: $I2CX,
                     pins: (SCL, SDA),
-                    freq: F,
+                    mode: M,
                     clocks: Clocks,
                     apb1: &mut APB1,
                 ) -> Self where
-                    F: Into<Hertz>,
+                    M: Into<Mode>,
                     SCL: SclPin<$I2CX>,
                     SDA: SdaPin<$I2CX>,
                 {
@@ -84,90 +180,367 @@ macro_rules! hal {
                     apb1.rstr().modify(|_, w| w.$i2cXrst().set_bit());
                     apb1.rstr().modify(|_, w| w.$i2cXrst().clear_bit());
 
-                    // let freq = freq.into().0;
-
-                    // assert!(freq <= 1_000_000);
-
-                    // // TODO review compliance with the timing requirements of I2C
-                    // // t_I2CCLK = 1 / PCLK1
-                    // // t_PRESC  = (PRESC + 1) * t_I2CCLK
-                    // // t_SCLL   = (SCLL + 1) * t_PRESC
-                    // // t_SCLH   = (SCLH + 1) * t_PRESC
-                    // //
-                    // // t_SYNC1 + t_SYNC2 > 4 * t_I2CCLK
-                    // // t_SCL ~= t_SYNC1 + t_SYNC2 + t_SCLL + t_SCLH
-                    // let i2cclk = clocks.pclk1().0;
-                    // let ratio = i2cclk / freq - 4;
-                    // let (presc, scll, sclh, sdadel, scldel) = if freq >= 100_000 {
-                    //     // fast-mode or fast-mode plus
-                    //     // here we pick SCLL + 1 = 2 * (SCLH + 1)
-                    //     let presc = ratio / 387;
-
-                    //     let sclh = ((ratio / (presc + 1)) - 3) / 3;
-                    //     let scll = 2 * (sclh + 1) - 1;
-
-                    //     let (sdadel, scldel) = if freq > 400_000 {
-                    //         // fast-mode plus
-                    //         let sdadel = 0;
-                    //         let scldel = i2cclk / 4_000_000 / (presc + 1) - 1;
-
-                    //         (sdadel, scldel)
-                    //     } else {
-                    //         // fast-mode
-                    //         let sdadel = i2cclk / 8_000_000 / (presc + 1);
-                    //         let scldel = i2cclk / 2_000_000 / (presc + 1) - 1;
-
-                    //         (sdadel, scldel)
-                    //     };
-
-                    //     (presc, scll, sclh, sdadel, scldel)
-                    // } else {
-                    //     // standard-mode
-                    //     // here we pick SCLL = SCLH
-                    //     let presc = ratio / 514;
-
-                    //     let sclh = ((ratio / (presc + 1)) - 2) / 2;
-                    //     let scll = sclh;
-
-                    //     let sdadel = i2cclk / 2_000_000 / (presc + 1);
-                    //     let scldel = i2cclk / 800_000 / (presc + 1) - 1;
-
-                    //     (presc, scll, sclh, sdadel, scldel)
-                    // };
-
-                    // let presc = u8(presc).unwrap();
-                    // assert!(presc < 16);
-                    // let scldel = u8(scldel).unwrap();
-                    // assert!(scldel < 16);
-                    // let sdadel = u8(sdadel).unwrap();
-                    // assert!(sdadel < 16);
-                    // let sclh = u8(sclh).unwrap();
-                    // let scll = u8(scll).unwrap();
-
-                    // // Configure for "fast mode" (400 KHz)
-                    // i2c.timingr.write(|w| unsafe {
-                    //     w.presc()
-                    //         .bits(presc)
-                    //         .scll()
-                    //         .bits(scll)
-                    //         .sclh()
-                    //         .bits(sclh)
-                    //         .sdadel()
-                    //         .bits(sdadel)
-                    //         .scldel()
-                    //         .bits(scldel)
-                    // });
+                    let mode = mode.into();
+                    let freq = mode.frequency().0;
+
+                    assert!(freq <= 400_000);
+
+                    // The peripheral is fed by PCLK1; CR2.FREQ must hold its value in MHz and be
+                    // in the 2..=36 range on the F103.
+                    let pclk1 = clocks.pclk1().0;
+                    let pclk1_mhz = (pclk1 / 1_000_000) as u16;
+                    assert!(pclk1_mhz >= 2 && pclk1_mhz <= 36);
+
+                    // The timing registers may only be touched while PE is cleared.
+                    i2c.cr1.write(|w| w.pe().clear_bit());
+
+                    i2c.cr2.write(|w| unsafe { w.freq().bits(pclk1_mhz as u8) });
+
+                    match mode {
+                        Mode::Standard { .. } => {
+                            // Standard mode: t_high == t_low, so CCR counts half an SCL period.
+                            let ccr = pclk1 / (freq * 2);
+                            let ccr = if ccr < 4 { 4 } else { ccr };
+
+                            // Maximum rise time in Sm is 1000 ns, i.e. t_pclk1 * (FREQ + 1).
+                            i2c.trise.write(|w| w.trise().bits((pclk1_mhz + 1) as u8));
+
+                            i2c.ccr.write(|w| unsafe {
+                                w.f_s().clear_bit().ccr().bits(ccr as u16)
+                            });
+                        }
+                        Mode::Fast { duty_cycle, .. } => {
+                            // Maximum rise time in Fm is 300 ns.
+                            i2c.trise.write(|w| {
+                                w.trise().bits(((pclk1_mhz * 300) / 1000 + 1) as u8)
+                            });
+
+                            match duty_cycle {
+                                DutyCycle::Ratio2to1 => {
+                                    // t_low == 2 * t_high
+                                    let ccr = pclk1 / (freq * 3);
+                                    let ccr = if ccr < 1 { 1 } else { ccr };
+
+                                    i2c.ccr.write(|w| unsafe {
+                                        w.f_s().set_bit().duty().clear_bit().ccr().bits(ccr as u16)
+                                    });
+                                }
+                                DutyCycle::Ratio16to9 => {
+                                    // t_low / t_high == 16 / 9
+                                    let ccr = pclk1 / (freq * 25);
+                                    let ccr = if ccr < 1 { 1 } else { ccr };
+
+                                    i2c.ccr.write(|w| unsafe {
+                                        w.f_s().set_bit().duty().set_bit().ccr().bits(ccr as u16)
+                                    });
+                                }
+                            }
+                        }
+                    }
 
                     // Enable the peripheral
                     i2c.cr1.write(|w| w.pe().set_bit());
 
-                    I2c { i2c, pins }
+                    I2c { i2c, pins, tx: (), rx: () }
                 }
 
                 /// Releases the I2C peripheral and associated pins
                 pub fn free(self) -> ($I2CX, (SCL, SDA)) {
                     (self.i2c, self.pins)
                 }
+
+                /// Recovers the bus from a slave that is holding SDA low
+                ///
+                /// Disables the peripheral, drives SCL manually as an open-drain output and clocks
+                /// out up to nine pulses until the slave releases SDA, then generates a STOP
+                /// condition, restores the pins to their alternate function and re-enables the
+                /// peripheral.
+                pub fn recover(&mut self)
+                where
+                    SCL: SclPin<$I2CX>,
+                    SDA: SdaPin<$I2CX>,
+                {
+                    let scl = <SCL as SclPin<$I2CX>>::BIT;
+                    let sda = <SDA as SdaPin<$I2CX>>::BIT;
+
+                    // take manual control of the bus
+                    self.i2c.cr1.modify(|_, w| w.pe().clear_bit());
+
+                    let gpiob = unsafe { &*GPIOB::ptr() };
+
+                    let read_cfg = |bit: u8| -> u32 {
+                        let shift = (bit % 8) * 4;
+                        let bits = if bit < 8 {
+                            gpiob.crl.read().bits()
+                        } else {
+                            gpiob.crh.read().bits()
+                        };
+                        (bits >> shift) & 0xf
+                    };
+
+                    let write_cfg = |bit: u8, nibble: u32| {
+                        let shift = (bit % 8) * 4;
+                        if bit < 8 {
+                            gpiob.crl.modify(|r, w| unsafe {
+                                w.bits((r.bits() & !(0xf << shift)) | (nibble << shift))
+                            });
+                        } else {
+                            gpiob.crh.modify(|r, w| unsafe {
+                                w.bits((r.bits() & !(0xf << shift)) | (nibble << shift))
+                            });
+                        }
+                    };
+
+                    // remember the alternate-function configuration
+                    let scl_cfg = read_cfg(scl);
+                    let sda_cfg = read_cfg(sda);
+
+                    // reconfigure both lines as open-drain outputs (MODE = 0b11, CNF = 0b01)
+                    write_cfg(scl, 0b0111);
+                    write_cfg(sda, 0b0111);
+
+                    let high = |bit: u8| gpiob.bsrr.write(|w| unsafe { w.bits(1 << bit) });
+                    let low = |bit: u8| gpiob.brr.write(|w| unsafe { w.bits(1 << bit) });
+                    let sda_high = || gpiob.idr.read().bits() & (1 << sda) != 0;
+                    let delay = || for _ in 0..100 { asm::nop() };
+
+                    high(sda);
+                    high(scl);
+
+                    // pulse SCL until the slave releases SDA
+                    for _ in 0..9 {
+                        if sda_high() {
+                            break;
+                        }
+
+                        low(scl);
+                        delay();
+                        high(scl);
+                        delay();
+                    }
+
+                    // manual STOP: SDA transitions low -> high while SCL is high
+                    low(sda);
+                    delay();
+                    high(scl);
+                    delay();
+                    high(sda);
+                    delay();
+
+                    // restore the alternate function and resume normal operation
+                    write_cfg(scl, scl_cfg);
+                    write_cfg(sda, sda_cfg);
+
+                    self.i2c.cr1.modify(|_, w| w.pe().set_bit());
+                }
+            }
+
+            impl<PINS> I2c<$I2CX, PINS> {
+                /// Pairs the peripheral with its DMA1 TX/RX channels
+                ///
+                /// Once paired the peripheral can stream buffers through DMA instead of spinning
+                /// in `busy_wait!`, off-loading large EEPROM/display transfers from the CPU.
+                pub fn with_dma(
+                    self,
+                    tx: dma1::$txch,
+                    rx: dma1::$rxch,
+                ) -> I2c<$I2CX, PINS, dma1::$txch, dma1::$rxch> {
+                    let I2c { i2c, pins, .. } = self;
+                    I2c { i2c, pins, tx, rx }
+                }
+            }
+
+            impl<PINS> I2c<$I2CX, PINS, dma1::$txch, dma1::$rxch> {
+                /// Releases the I2C peripheral, pins and DMA channels
+                pub fn free(self) -> ($I2CX, PINS, dma1::$txch, dma1::$rxch) {
+                    (self.i2c, self.pins, self.tx, self.rx)
+                }
+
+                /// Writes `buffer` to the `addr`essed slave through the TX DMA channel
+                ///
+                /// Returns as soon as the channel is running; the transfer completes in the
+                /// background. Waiting on (or dropping) the returned handle emits the `STOP`.
+                pub fn write_all<B>(
+                    mut self,
+                    addr: u8,
+                    buffer: B,
+                ) -> Result<I2cDmaTransfer<R, B, Self>, Error>
+                where
+                    B: Static<[u8]>,
+                {
+                    {
+                        let slice = buffer.borrow();
+
+                        self.tx.cpar().write(|w| unsafe {
+                            w.bits(&self.i2c.dr as *const _ as usize as u32)
+                        });
+                        self.tx.cmar().write(|w| unsafe {
+                            w.bits(slice.as_ptr() as usize as u32)
+                        });
+                        self.tx.cndtr().write(|w| unsafe { w.bits(slice.len() as u16) });
+                        self.tx.ccr().modify(|_, w| {
+                            w.mem2mem()
+                                .clear_bit()
+                                .pl()
+                                .medium()
+                                .msize()
+                                .bit8()
+                                .psize()
+                                .bit8()
+                                .minc()
+                                .set_bit()
+                                .circ()
+                                .clear_bit()
+                                .dir()
+                                .set_bit()
+                        });
+                    }
+
+                    // On the F1 the DMA unit only drives the data phase, so the address handshake
+                    // is still done in software, exactly as in the blocking `write`.
+
+                    // START, wait for SB
+                    self.i2c.cr1.write(|w| w.start().set_bit());
+                    busy_wait!(self.i2c, sb);
+
+                    // slave address, wait for ADDR
+                    self.i2c.dr.write(|w| unsafe { w.dr().bits(addr << 1) });
+                    busy_wait!(self.i2c, addr);
+
+                    // clear ADDR, then let DMA feed the data register
+                    self.i2c.sr1.read();
+                    self.i2c.sr2.read();
+
+                    self.i2c.cr2.modify(|_, w| w.dmaen().set_bit());
+                    self.tx.ccr().modify(|_, w| w.en().set_bit());
+
+                    // hand the channel back while it is still streaming
+                    Ok(I2cDmaTransfer { inner: Some(DmaTransfer::r(buffer, self)) })
+                }
+
+                /// Emits the closing STOP of a TX DMA transfer and tears the channel down
+                fn finish_tx(&mut self) {
+                    // wait for the last byte to leave the shift register before the STOP
+                    while self.i2c.sr1.read().btf().bit_is_clear() {}
+                    self.i2c.cr1.write(|w| w.stop().set_bit());
+                    self.tx.ccr().modify(|_, w| w.en().clear_bit());
+                    self.i2c.cr2.modify(|_, w| w.dmaen().clear_bit());
+                }
+
+                /// Reads from the `addr`essed slave into `buffer` through the RX DMA channel
+                ///
+                /// Returns as soon as the channel is running; the transfer completes in the
+                /// background. Waiting on (or dropping) the returned handle emits the `STOP`.
+                pub fn read_exact<B>(
+                    mut self,
+                    addr: u8,
+                    mut buffer: B,
+                ) -> Result<I2cDmaTransfer<W, B, Self>, Error>
+                where
+                    B: Static<[u8]>,
+                {
+                    {
+                        let slice = buffer.borrow_mut();
+
+                        self.rx.cpar().write(|w| unsafe {
+                            w.bits(&self.i2c.dr as *const _ as usize as u32)
+                        });
+                        self.rx.cmar().write(|w| unsafe {
+                            w.bits(slice.as_mut_ptr() as usize as u32)
+                        });
+                        self.rx.cndtr().write(|w| unsafe { w.bits(slice.len() as u16) });
+                        self.rx.ccr().modify(|_, w| {
+                            w.mem2mem()
+                                .clear_bit()
+                                .pl()
+                                .medium()
+                                .msize()
+                                .bit8()
+                                .psize()
+                                .bit8()
+                                .minc()
+                                .set_bit()
+                                .circ()
+                                .clear_bit()
+                                .dir()
+                                .clear_bit()
+                        });
+                    }
+
+                    // The address handshake is software-driven even on the DMA path, mirroring the
+                    // blocking `read`.
+
+                    // START, wait for SB
+                    self.i2c.cr1.write(|w| w.ack().set_bit().start().set_bit());
+                    busy_wait!(self.i2c, sb);
+
+                    // slave address, wait for ADDR
+                    self.i2c.dr.write(|w| unsafe { w.dr().bits((addr << 1) | 1) });
+                    busy_wait!(self.i2c, addr);
+
+                    // clear ADDR; LAST makes the controller NACK the final byte that DMA requests
+                    self.i2c.sr1.read();
+                    self.i2c.sr2.read();
+
+                    self.i2c.cr2.modify(|_, w| w.dmaen().set_bit().last().set_bit());
+                    self.rx.ccr().modify(|_, w| w.en().set_bit());
+
+                    // hand the channel back while it is still streaming
+                    Ok(I2cDmaTransfer { inner: Some(DmaTransfer::w(buffer, self)) })
+                }
+
+                /// Emits the closing STOP of an RX DMA transfer and tears the channel down
+                fn finish_rx(&mut self) {
+                    self.i2c.cr1.write(|w| w.stop().set_bit());
+                    self.rx.ccr().modify(|_, w| w.en().clear_bit());
+                    self.i2c.cr2.modify(|_, w| w.dmaen().clear_bit().last().clear_bit());
+                }
+            }
+
+            impl<PINS, B> I2cDmaTransfer<R, B, I2c<$I2CX, PINS, dma1::$txch, dma1::$rxch>> {
+                /// Returns `true` once the DMA channel has moved the whole buffer
+                pub fn is_done(&self) -> bool {
+                    self.inner.as_ref().unwrap().is_done()
+                }
+
+                /// Blocks until the transfer is done, emits the `STOP` and releases the peripheral
+                pub fn wait(mut self) -> (B, I2c<$I2CX, PINS, dma1::$txch, dma1::$rxch>) {
+                    let (buffer, mut i2c) = self.inner.take().unwrap().wait();
+                    i2c.finish_tx();
+                    (buffer, i2c)
+                }
+            }
+
+            impl<PINS, B> Drop for I2cDmaTransfer<R, B, I2c<$I2CX, PINS, dma1::$txch, dma1::$rxch>> {
+                fn drop(&mut self) {
+                    if let Some(inner) = self.inner.take() {
+                        let (_buffer, mut i2c) = inner.wait();
+                        i2c.finish_tx();
+                    }
+                }
+            }
+
+            impl<PINS, B> I2cDmaTransfer<W, B, I2c<$I2CX, PINS, dma1::$txch, dma1::$rxch>> {
+                /// Returns `true` once the DMA channel has filled the whole buffer
+                pub fn is_done(&self) -> bool {
+                    self.inner.as_ref().unwrap().is_done()
+                }
+
+                /// Blocks until the transfer is done, emits the `STOP` and releases the peripheral
+                pub fn wait(mut self) -> (B, I2c<$I2CX, PINS, dma1::$txch, dma1::$rxch>) {
+                    let (buffer, mut i2c) = self.inner.take().unwrap().wait();
+                    i2c.finish_rx();
+                    (buffer, i2c)
+                }
+            }
+
+            impl<PINS, B> Drop for I2cDmaTransfer<W, B, I2c<$I2CX, PINS, dma1::$txch, dma1::$rxch>> {
+                fn drop(&mut self) {
+                    if let Some(inner) = self.inner.take() {
+                        let (_buffer, mut i2c) = inner.wait();
+                        i2c.finish_rx();
+                    }
+                }
             }
 
             impl<PINS> Write for I2c<$I2CX, PINS> {
@@ -204,6 +577,91 @@ macro_rules! hal {
                 }
             }
 
+            impl<PINS> Read for I2c<$I2CX, PINS> {
+                type Error = Error;
+
+                fn read(&mut self, addr: u8, buffer: &mut [u8]) -> Result<(), Error> {
+                    self.i2c.cr1.write(|w| w.ack().set_bit());
+
+                    // START, wait for SB
+                    self.i2c.cr1.write(|w| w.start().set_bit());
+                    busy_wait!(self.i2c, sb);
+
+                    // slave address, wait for ADDR
+                    self.i2c.dr.write(|w| unsafe { w.dr().bits((addr << 1) | 1) });
+                    busy_wait!(self.i2c, addr);
+
+                    match buffer.len() {
+                        1 => {
+                            self.i2c.cr1.write(|w| w.ack().clear_bit());
+                        }
+                        2 => {
+                            self.i2c.cr1.write(|w| w.ack().clear_bit());
+                            self.i2c.cr1.write(|w| w.pos().set_bit());
+                        }
+                        _ => {}
+                    }
+
+                    // clear ADDR
+                    self.i2c.sr1.read();
+                    self.i2c.sr2.read();
+
+                    if buffer.len() > 3 {
+                        for byte in &mut buffer[3..] {
+                            busy_wait!(self.i2c, rx_ne);
+
+                            *byte = self.i2c.dr.read().dr().bits();
+                        }
+                    }
+
+                    match buffer.len() {
+                        1 => {
+                            busy_wait!(self.i2c, rx_ne);
+
+                            // STOP
+                            self.i2c.cr1.write(|w| w.stop().set_bit());
+
+                            buffer[0] = self.i2c.dr.read().dr().bits();
+                        }
+                        2 => {
+                            busy_wait!(self.i2c, rx_ne);
+
+                            busy_wait!(self.i2c, btf);
+
+                            // STOP
+                            self.i2c.cr1.write(|w| w.stop().set_bit());
+
+                            buffer[0] = self.i2c.dr.read().dr().bits();
+                            buffer[1] = self.i2c.dr.read().dr().bits();
+                        }
+                        3 => {
+                            busy_wait!(self.i2c, rx_ne);
+
+                            busy_wait!(self.i2c, btf);
+
+                            self.i2c.cr1.write(|w| w.ack().clear_bit());
+
+                            busy_wait!(self.i2c, rx_ne);
+
+                            busy_wait!(self.i2c, btf);
+
+                            // STOP
+                            self.i2c.cr1.write(|w| w.stop().set_bit());
+
+                            buffer[0] = self.i2c.dr.read().dr().bits();
+                            buffer[1] = self.i2c.dr.read().dr().bits();
+
+                            busy_wait!(self.i2c, rx_ne);
+
+                            buffer[2] = self.i2c.dr.read().dr().bits();
+                        }
+                        _ => {}
+                    }
+
+                    Ok(())
+                }
+            }
+
             impl<PINS> WriteRead for I2c<$I2CX, PINS> {
                 type Error = Error;
 
@@ -311,6 +769,10 @@ macro_rules! hal {
 
                             buffer[0] = self.i2c.dr.read().dr().bits();
                             buffer[1] = self.i2c.dr.read().dr().bits();
+
+                            busy_wait!(self.i2c, rx_ne);
+
+                            buffer[2] = self.i2c.dr.read().dr().bits();
                         }
                         _ => {}
                     }
@@ -323,6 +785,8 @@ macro_rules! hal {
 }
 
 hal! {
-    I2C1: (i2c1, i2c1en, i2c1rst),
-    I2C2: (i2c2, i2c2en, i2c2rst),
+    // I2C1_TX -> DMA1 channel 6, I2C1_RX -> DMA1 channel 7
+    I2C1: (i2c1, i2c1en, i2c1rst, C6, C7),
+    // I2C2_TX -> DMA1 channel 4, I2C2_RX -> DMA1 channel 5
+    I2C2: (i2c2, i2c2en, i2c2rst, C4, C5),
 }